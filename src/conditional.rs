@@ -0,0 +1,109 @@
+use std::collections::hash_map::DefaultHasher;
+use std::convert::Infallible;
+use std::hash::{Hash, Hasher};
+
+use chrono::{DateTime, Timelike, Utc};
+use rocket::http::{Header, Status};
+use rocket::request::{FromRequest, Outcome};
+use rocket::response::{self, Responder};
+use rocket::serde::Serialize;
+use rocket::serde::json::{Json, serde_json};
+use rocket::{Request, Response};
+
+/// Request guard for conditional-GET validators (`If-None-Match` /
+/// `If-Modified-Since`), parsed once per request.
+pub struct ConditionalGet {
+    if_none_match: Option<String>,
+    if_modified_since: Option<DateTime<Utc>>,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ConditionalGet {
+    type Error = Infallible;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let if_none_match = req
+            .headers()
+            .get_one("If-None-Match")
+            .map(|v| v.trim().trim_matches('"').to_string());
+
+        let if_modified_since = req
+            .headers()
+            .get_one("If-Modified-Since")
+            .and_then(|v| DateTime::parse_from_rfc2822(v).ok())
+            .map(|dt| dt.with_timezone(&Utc));
+
+        Outcome::Success(ConditionalGet {
+            if_none_match,
+            if_modified_since,
+        })
+    }
+}
+
+impl ConditionalGet {
+    /// Whether the client's validators show it already holds this exact
+    /// representation, last changed at `last_modified`. `If-None-Match`
+    /// takes precedence over `If-Modified-Since` when both are present, per
+    /// RFC 7232 §3.3.
+    fn matches(&self, etag: &str, last_modified: DateTime<Utc>) -> bool {
+        if let Some(ref tag) = self.if_none_match {
+            return tag == etag;
+        }
+        if let Some(ims) = self.if_modified_since {
+            return last_modified <= ims;
+        }
+        false
+    }
+}
+
+/// Hashes a serializable payload into a weak content ETag.
+fn etag_for<T: Serialize>(payload: &T) -> String {
+    let bytes = serde_json::to_vec(payload).unwrap_or_default();
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// Wraps a JSON body with `ETag` / `Last-Modified` headers, answering
+/// `304 Not Modified` with an empty body when the request's validators show
+/// the client's cached copy is still current.
+pub struct Conditional<T> {
+    data: T,
+    etag: String,
+    last_modified: DateTime<Utc>,
+    not_modified: bool,
+}
+
+impl<T: Serialize> Conditional<T> {
+    pub fn new(data: T, last_modified: DateTime<Utc>, guard: &ConditionalGet) -> Self {
+        // `Last-Modified` (and `If-Modified-Since`, once round-tripped through
+        // a client) only carries whole-second precision, so truncate here to
+        // keep the value we compare against consistent with the one we emit.
+        let last_modified = last_modified.with_nanosecond(0).unwrap_or(last_modified);
+
+        let etag = etag_for(&data);
+        let not_modified = guard.matches(&etag, last_modified);
+
+        Conditional {
+            data,
+            etag,
+            last_modified,
+            not_modified,
+        }
+    }
+}
+
+impl<'r, T: Serialize> Responder<'r, 'static> for Conditional<T> {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        let mut response = if self.not_modified {
+            Response::build().status(Status::NotModified).finalize()
+        } else {
+            Json(self.data).respond_to(req)?
+        };
+
+        response.set_header(Header::new("ETag", format!("\"{}\"", self.etag)));
+        response.set_header(Header::new("Last-Modified", self.last_modified.to_rfc2822()));
+
+        Ok(response)
+    }
+}
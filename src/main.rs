@@ -1,47 +1,109 @@
 #[macro_use]
 extern crate rocket;
 
-use std::sync::{Mutex, PoisonError};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
 use std::time::{Duration, Instant};
 
+use chrono::{DateTime, Utc};
 use dotenvy::dotenv;
 use reqwest::Client;
 use rocket::State;
+use rocket::Shutdown;
+use rocket::fairing::AdHoc;
 use rocket::http::Status;
 use rocket::response::status;
+use rocket::response::stream::{Event, EventStream};
 use rocket::serde::Serialize;
-use rocket::serde::json::Json;
+use rocket::serde::json::{Json, serde_json};
+use rocket::tokio::select;
+use rocket::tokio::sync::broadcast::{self, error::RecvError};
+use rocket::tokio::time::interval;
 
+mod conditional;
+mod persist;
 mod tfl;
 
+use conditional::{Conditional, ConditionalGet};
 use tfl::Arrival;
 
+/// How often a keep-alive comment is sent on idle SSE connections so proxies
+/// and load balancers don't time out the connection.
+const STREAM_KEEPALIVE: Duration = Duration::from_secs(15);
+
+/// Ceiling on the poller's exponential backoff after repeated upstream
+/// failures, so a prolonged TfL outage doesn't back off forever.
+const POLLER_MAX_BACKOFF: Duration = Duration::from_secs(300);
+
 #[derive(Clone)]
 struct ApiConfig {
-    stop_id: String,
+    stop_ids: Vec<String>,
     app_id: Option<String>,
     app_key: Option<String>,
     cache_ttl: Duration,
+    cache_path: Option<String>,
 }
 
 impl ApiConfig {
-    fn build_tfl_url(&self) -> String {
-        let stop_id = &self.stop_id;
+    fn build_tfl_url(&self, stop_id: &str) -> String {
         let app_id = self.app_id.as_deref();
         let app_key = self.app_key.as_deref();
         tfl::build_tfl_url(stop_id, app_id, app_key)
     }
+
+    fn build_stop_search_url(&self, query: &str) -> String {
+        tfl::journey::build_stop_search_url(query, self.app_id.as_deref(), self.app_key.as_deref())
+    }
+
+    fn build_journey_url(&self, from_id: &str, to_id: &str, time: Option<&str>) -> String {
+        tfl::journey::build_journey_url(
+            from_id,
+            to_id,
+            time,
+            self.app_id.as_deref(),
+            self.app_key.as_deref(),
+        )
+    }
+
+    /// The stop used by the legacy single-stop endpoints (`/next-bus`,
+    /// `/next-bus/summary`, `/next-bus/stream`): the first configured id.
+    fn primary_stop_id(&self) -> &str {
+        &self.stop_ids[0]
+    }
+
+    fn is_known_stop(&self, stop_id: &str) -> bool {
+        self.stop_ids.iter().any(|id| id == stop_id)
+    }
 }
 
 struct Cache {
     last_fetch: Instant,
+    fetched_at: DateTime<Utc>,
     data: Vec<Arrival>,
 }
 
+/// A cached batch of arrivals plus the wall-clock time it was fetched at,
+/// used as the `Last-Modified` validator for conditional GETs.
+struct CachedArrivals {
+    data: Vec<Arrival>,
+    fetched_at: DateTime<Utc>,
+}
+
 struct AppState {
     client: Client,
     config: ApiConfig,
-    cache: Mutex<Option<Cache>>,
+    cache: Mutex<HashMap<String, Cache>>,
+    arrivals_tx: broadcast::Sender<Vec<Arrival>>,
+    poller_status: Mutex<HashMap<String, PollerStatus>>,
+}
+
+/// Tracks the health of one stop's background poll loop so `/health` and the
+/// cached handlers can report why data might be missing or stale. Kept
+/// per-stop so one failing stop's backoff never starves the others.
+#[derive(Default, Clone)]
+struct PollerStatus {
+    consecutive_failures: u32,
+    last_error: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -64,6 +126,21 @@ impl From<tfl::TflError> for ErrorResponse {
                 message: "Failed to parse TfL response JSON".into(),
                 details: Some(e.to_string()),
             },
+            tfl::TflError::NoJourney => ErrorResponse {
+                error: "NO_JOURNEY".into(),
+                message: "No journey found between the given places".into(),
+                details: None,
+            },
+        }
+    }
+}
+
+impl tfl::TflError {
+    /// The HTTP status a given `TflError` should surface as.
+    fn status(&self) -> Status {
+        match self {
+            tfl::TflError::UpstreamError(..) | tfl::TflError::ParseError(..) => Status::BadGateway,
+            tfl::TflError::NoJourney => Status::NotFound,
         }
     }
 }
@@ -88,58 +165,149 @@ impl<T> From<PoisonError<T>> for ErrorResponse {
     }
 }
 
-async fn check_arrivals_cache(
-    state: &State<AppState>,
-) -> Result<Option<Vec<Arrival>>, status::Custom<Json<ErrorResponse>>> {
-    let cache_guard = state
-        .cache
-        .lock()
-        .map_err(|e| status::Custom(Status::InternalServerError, Json(ErrorResponse::from(e))))?;
-
-    if let Some(cache) = cache_guard.as_ref() {
-        if cache.last_fetch.elapsed() < state.config.cache_ttl {
-            return Ok(Some(cache.data.clone()));
-        }
+/// Writes a freshly fetched batch of arrivals for `stop_id` into the cache,
+/// persisting it to disk if `TFL_CACHE_PATH` is configured. The primary
+/// stop's data is also published to any subscribed SSE streams. Shared
+/// between the background poller and (indirectly, via that poller) every
+/// HTTP handler.
+fn write_arrivals_cache(
+    state: &AppState,
+    stop_id: &str,
+    arrivals: Vec<Arrival>,
+) -> Result<(), String> {
+    let fetched_at = Utc::now();
+
+    let mut cache_guard = state.cache.lock().map_err(|e| e.to_string())?;
+
+    cache_guard.insert(
+        stop_id.to_string(),
+        Cache {
+            last_fetch: Instant::now(),
+            fetched_at,
+            data: arrivals.clone(),
+        },
+    );
+    drop(cache_guard);
+
+    persist::store(stop_id, &arrivals, fetched_at);
+
+    if stop_id == state.config.primary_stop_id() {
+        // Ignore send errors: they just mean no stream subscribers are connected.
+        let _ = state.arrivals_tx.send(arrivals);
     }
 
-    Ok(None)
+    Ok(())
 }
 
-async fn update_arrivals_cache(
-    state: &State<AppState>,
-    arrivals: Vec<Arrival>,
-) -> Result<(), status::Custom<Json<ErrorResponse>>> {
-    let mut cache_guard = state
+/// Reads the cache that the background poller keeps warm for `stop_id`.
+/// Handlers never call out to TfL themselves, so this never blocks on the
+/// network; if the poller hasn't populated the cache yet (or upstream has
+/// been failing) the caller gets a `CACHE_UNAVAILABLE` error carrying the
+/// poller's last error.
+async fn fetch_arrivals_from_tfl(
+    state: &AppState,
+    stop_id: &str,
+) -> Result<CachedArrivals, status::Custom<Json<ErrorResponse>>> {
+    let cache_guard = state
         .cache
         .lock()
         .map_err(|e| status::Custom(Status::InternalServerError, Json(ErrorResponse::from(e))))?;
 
-    *cache_guard = Some(Cache {
-        last_fetch: Instant::now(),
-        data: arrivals.clone(),
-    });
+    if let Some(cache) = cache_guard.get(stop_id) {
+        return Ok(CachedArrivals {
+            data: cache.data.clone(),
+            fetched_at: cache.fetched_at,
+        });
+    }
+    drop(cache_guard);
 
-    Ok(())
+    let last_error = state
+        .poller_status
+        .lock()
+        .map_err(|e| status::Custom(Status::InternalServerError, Json(ErrorResponse::from(e))))?
+        .get(stop_id)
+        .and_then(|status| status.last_error.clone());
+
+    Err(status::Custom(
+        Status::ServiceUnavailable,
+        Json(ErrorResponse {
+            error: "CACHE_UNAVAILABLE".into(),
+            message: "Arrivals cache has not been populated yet".into(),
+            details: last_error,
+        }),
+    ))
 }
 
-async fn fetch_arrivals_from_tfl(
-    state: &State<AppState>,
-) -> Result<Vec<Arrival>, status::Custom<Json<ErrorResponse>>> {
-    // Check Cache
-    if let Some(cached) = check_arrivals_cache(state).await? {
-        return Ok(cached);
-    }
-
-    // Fetch from TfL
-    let arrivals = tfl::fetch_arrivals(&state.config, &state.client)
-        .await
-        .map_err(|e| status::Custom(Status::BadGateway, Json(ErrorResponse::from(e))))?;
+/// Backoff applied after `consecutive_failures` in a row. Floored at
+/// `cache_ttl` (a single blip shouldn't poll TfL more eagerly than the normal
+/// cadence) and doubling from there, up to [`POLLER_MAX_BACKOFF`].
+fn poller_backoff(consecutive_failures: u32, cache_ttl: Duration) -> Duration {
+    let exponent = consecutive_failures.saturating_sub(1).min(8);
+    cache_ttl
+        .saturating_mul(2u32.saturating_pow(exponent))
+        .min(POLLER_MAX_BACKOFF)
+}
 
-    // Update Cache
-    update_arrivals_cache(state, arrivals.clone()).await?;
+/// Background task that keeps one stop's arrivals cache warm so request
+/// handlers never pay upstream latency. Loops on `config.cache_ttl` while
+/// upstream is healthy, and backs off exponentially on repeated failures for
+/// *this* stop only, recording the last error for `/health`. Run as one
+/// independent task per configured stop, so a failing stop's backoff never
+/// delays the refresh of the others.
+async fn poll_stop(state: Arc<AppState>, stop_id: String) {
+    loop {
+        let seeded_and_fresh = state
+            .cache
+            .lock()
+            .ok()
+            .and_then(|cache| cache.get(&stop_id).map(|c| c.last_fetch.elapsed() < state.config.cache_ttl))
+            .unwrap_or(false);
+
+        let failed = if seeded_and_fresh {
+            false
+        } else {
+            match tfl::fetch_arrivals(&state.config, &state.client, &stop_id).await {
+                Ok(arrivals) => {
+                    if let Err(e) = write_arrivals_cache(&state, &stop_id, arrivals) {
+                        rocket::error!("failed to write arrivals cache for stop {}: {}", stop_id, e);
+                    }
+                    false
+                }
+                Err(e) => {
+                    rocket::error!("failed to poll TfL arrivals for stop {}: {}", stop_id, e);
+                    if let Ok(mut statuses) = state.poller_status.lock() {
+                        let status = statuses.entry(stop_id.clone()).or_default();
+                        status.last_error = Some(format!("stop {}: {}", stop_id, e));
+                    }
+                    true
+                }
+            }
+        };
+
+        let sleep_for = match state.poller_status.lock() {
+            Ok(mut statuses) => {
+                let status = statuses.entry(stop_id.clone()).or_default();
+                if failed {
+                    status.consecutive_failures += 1;
+                    poller_backoff(status.consecutive_failures, state.config.cache_ttl)
+                } else {
+                    status.consecutive_failures = 0;
+                    status.last_error = None;
+                    state.config.cache_ttl
+                }
+            }
+            Err(_) => state.config.cache_ttl,
+        };
+
+        rocket::tokio::time::sleep(sleep_for).await;
+    }
+}
 
-    // Return arrivals
-    Ok(arrivals)
+/// Spawns one independent [`poll_stop`] task per configured stop.
+fn run_poller(state: Arc<AppState>) {
+    for stop_id in state.config.stop_ids.clone() {
+        rocket::tokio::spawn(poll_stop(state.clone(), stop_id));
+    }
 }
 
 fn filter_arrivals_by_route(arrivals: Vec<Arrival>, routes: &str) -> Vec<Arrival> {
@@ -156,16 +324,17 @@ fn filter_arrivals_by_route(arrivals: Vec<Arrival>, routes: &str) -> Vec<Arrival
 #[get("/next-bus?<routes>")]
 async fn next_bus(
     routes: Option<String>,
-    state: &State<AppState>,
-) -> Result<Json<Vec<Arrival>>, status::Custom<Json<ErrorResponse>>> {
+    state: &State<Arc<AppState>>,
+    conditional: ConditionalGet,
+) -> Result<Conditional<Vec<Arrival>>, status::Custom<Json<ErrorResponse>>> {
     // Fetch arrivals
-    let arrivals = fetch_arrivals_from_tfl(state).await?;
+    let cached = fetch_arrivals_from_tfl(state, state.config.primary_stop_id()).await?;
 
     // Filter by route if provided
     let mut filtered = if let Some(ref routes_str) = routes {
-        filter_arrivals_by_route(arrivals, routes_str)
+        filter_arrivals_by_route(cached.data, routes_str)
     } else {
-        arrivals
+        cached.data
     };
 
     // Construct empty response if no arrivals
@@ -176,7 +345,62 @@ async fn next_bus(
                 error: "NO_ARRIVALS".into(),
                 message: format!(
                     "No upcoming buses found for stop {}{}",
-                    state.config.stop_id,
+                    state.config.primary_stop_id(),
+                    routes
+                        .as_ref()
+                        .map(|r| format!(" on route(s) {}", r))
+                        .unwrap_or_default()
+                ),
+                details: None,
+            }),
+        ));
+    }
+
+    // Sort by soonest
+    filtered.sort_by_key(|a| a.time_to_station);
+
+    Ok(Conditional::new(filtered, cached.fetched_at, &conditional))
+}
+
+/// Board-style endpoint serving any of the stops configured via
+/// `TFL_STOP_IDS`, rather than just the primary one `next_bus` reports on.
+#[get("/stops/<stop_id>/next-bus?<routes>")]
+async fn stop_next_bus(
+    stop_id: String,
+    routes: Option<String>,
+    state: &State<Arc<AppState>>,
+    conditional: ConditionalGet,
+) -> Result<Conditional<Vec<Arrival>>, status::Custom<Json<ErrorResponse>>> {
+    if !state.config.is_known_stop(&stop_id) {
+        return Err(status::Custom(
+            Status::NotFound,
+            Json(ErrorResponse {
+                error: "UNKNOWN_STOP".into(),
+                message: format!("Stop {} is not configured on this deployment", stop_id),
+                details: None,
+            }),
+        ));
+    }
+
+    // Fetch arrivals
+    let cached = fetch_arrivals_from_tfl(state, &stop_id).await?;
+
+    // Filter by route if provided
+    let mut filtered = if let Some(ref routes_str) = routes {
+        filter_arrivals_by_route(cached.data, routes_str)
+    } else {
+        cached.data
+    };
+
+    // Construct empty response if no arrivals
+    if filtered.is_empty() {
+        return Err(status::Custom(
+            Status::NotFound,
+            Json(ErrorResponse {
+                error: "NO_ARRIVALS".into(),
+                message: format!(
+                    "No upcoming buses found for stop {}{}",
+                    stop_id,
                     routes
                         .as_ref()
                         .map(|r| format!(" on route(s) {}", r))
@@ -190,7 +414,47 @@ async fn next_bus(
     // Sort by soonest
     filtered.sort_by_key(|a| a.time_to_station);
 
-    Ok(Json(filtered))
+    Ok(Conditional::new(filtered, cached.fetched_at, &conditional))
+}
+
+/// Streams live arrival updates as Server-Sent Events, instead of requiring
+/// clients to poll `next_bus`. Each event carries the routes-filtered batch
+/// of arrivals as JSON under the `arrivals` event name; idle connections get
+/// a keep-alive comment every [`STREAM_KEEPALIVE`] so they survive proxies.
+#[get("/next-bus/stream?<routes>")]
+fn next_bus_stream(routes: Option<String>, state: &State<Arc<AppState>>, mut end: Shutdown) -> EventStream![] {
+    let mut rx = state.arrivals_tx.subscribe();
+
+    EventStream! {
+        let mut keepalive = interval(STREAM_KEEPALIVE);
+        keepalive.tick().await;
+
+        loop {
+            let arrivals = select! {
+                msg = rx.recv() => match msg {
+                    Ok(arrivals) => arrivals,
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
+                },
+                _ = keepalive.tick() => {
+                    yield Event::comment("keep-alive");
+                    continue;
+                }
+                _ = &mut end => break,
+            };
+
+            let filtered = match &routes {
+                Some(r) => filter_arrivals_by_route(arrivals, r),
+                None => arrivals,
+            };
+
+            let Ok(data) = serde_json::to_string(&filtered) else {
+                continue;
+            };
+
+            yield Event::data(data).event("arrivals");
+        }
+    }
 }
 
 #[derive(Serialize)]
@@ -212,26 +476,28 @@ struct SummaryResponse {
 async fn next_bus_summary(
     routes: Option<String>,
     limit: Option<usize>,
-    state: &State<AppState>,
-) -> Result<Json<SummaryResponse>, status::Custom<Json<ErrorResponse>>> {
+    state: &State<Arc<AppState>>,
+    conditional: ConditionalGet,
+) -> Result<Conditional<SummaryResponse>, status::Custom<Json<ErrorResponse>>> {
     // Fetch arrivals
-    let arrivals = fetch_arrivals_from_tfl(state).await?;
+    let cached = fetch_arrivals_from_tfl(state, state.config.primary_stop_id()).await?;
 
     // Filter by route if provided
     let mut filtered = if let Some(ref routes_str) = routes {
-        filter_arrivals_by_route(arrivals, routes_str)
+        filter_arrivals_by_route(cached.data, routes_str)
     } else {
-        arrivals
+        cached.data
     };
 
     // Construct empty response if no arrivals
     if filtered.is_empty() {
-        return Ok(Json(SummaryResponse {
-            stop_id: state.config.stop_id.clone(),
+        let resp = SummaryResponse {
+            stop_id: state.config.primary_stop_id().to_string(),
             stop_name: "NA".into(),
-            last_updated: chrono::Utc::now().to_rfc3339(),
+            last_updated: Utc::now().to_rfc3339(),
             services: vec![],
-        }));
+        };
+        return Ok(Conditional::new(resp, cached.fetched_at, &conditional));
     }
 
     // Sort by soonest
@@ -257,7 +523,74 @@ async fn next_bus_summary(
         services,
     };
 
-    Ok(Json(resp))
+    Ok(Conditional::new(resp, cached.fetched_at, &conditional))
+}
+
+#[derive(Serialize)]
+struct StopHealth {
+    consecutive_failures: u32,
+    last_error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct HealthResponse {
+    status: &'static str,
+    stops: HashMap<String, StopHealth>,
+}
+
+/// Reports each configured stop's poller health: `ok` if every stop's last
+/// poll succeeded, `degraded` if any stop is currently backing off after
+/// consecutive failures.
+#[get("/health")]
+fn health(
+    state: &State<Arc<AppState>>,
+) -> Result<Json<HealthResponse>, status::Custom<Json<ErrorResponse>>> {
+    let statuses = state
+        .poller_status
+        .lock()
+        .map_err(|e| status::Custom(Status::InternalServerError, Json(ErrorResponse::from(e))))?;
+
+    let stops: HashMap<String, StopHealth> = state
+        .config
+        .stop_ids
+        .iter()
+        .map(|stop_id| {
+            let status = statuses.get(stop_id).cloned().unwrap_or_default();
+            (
+                stop_id.clone(),
+                StopHealth {
+                    consecutive_failures: status.consecutive_failures,
+                    last_error: status.last_error,
+                },
+            )
+        })
+        .collect();
+
+    let status = if stops.values().all(|s| s.consecutive_failures == 0) {
+        "ok"
+    } else {
+        "degraded"
+    };
+
+    Ok(Json(HealthResponse { status, stops }))
+}
+
+/// Plans multi-leg journeys between two human-typed place names, unlike the
+/// NaPTAN-id-keyed arrivals endpoints. Resolution and itinerary lookup both
+/// go straight to TfL; there's no cache here since routes don't repeat the
+/// way a single stop's arrivals do.
+#[get("/journey?<from>&<to>&<time>")]
+async fn journey(
+    from: String,
+    to: String,
+    time: Option<String>,
+    state: &State<Arc<AppState>>,
+) -> Result<Json<Vec<tfl::journey::Journey>>, status::Custom<Json<ErrorResponse>>> {
+    let journeys = tfl::journey::plan_journey(&state.config, &state.client, &from, &to, time.as_deref())
+        .await
+        .map_err(|e| status::Custom(e.status(), Json(ErrorResponse::from(e))))?;
+
+    Ok(Json(journeys))
 }
 
 fn load_config() -> ApiConfig {
@@ -265,15 +598,31 @@ fn load_config() -> ApiConfig {
 
     dotenv().ok();
 
-    let stop_id = env::var("TFL_STOP_ID").expect("TFL_STOP_ID must be set (TfL StopPoint id)");
+    // TFL_STOP_IDS takes a comma-separated list so one deployment can serve
+    // several stops; TFL_STOP_ID is still honoured for single-stop setups.
+    let stop_ids: Vec<String> = env::var("TFL_STOP_IDS")
+        .ok()
+        .filter(|s| !s.is_empty())
+        .and_then(|s| {
+            let ids: Vec<String> = s
+                .split(',')
+                .map(|id| id.trim().to_string())
+                .filter(|id| !id.is_empty())
+                .collect();
+            (!ids.is_empty()).then_some(ids)
+        })
+        .or_else(|| env::var("TFL_STOP_ID").ok().filter(|s| !s.is_empty()).map(|id| vec![id]))
+        .expect("TFL_STOP_IDS or TFL_STOP_ID must be set (comma-separated TfL StopPoint ids)");
 
     let app_id = env::var("TFL_APP_ID").ok().filter(|s| !s.is_empty());
     let app_key = env::var("TFL_APP_KEY").ok().filter(|s| !s.is_empty());
+    let cache_path = env::var("TFL_CACHE_PATH").ok().filter(|s| !s.is_empty());
 
     ApiConfig {
-        stop_id,
+        stop_ids,
         app_id,
         app_key,
+        cache_path,
         cache_ttl: Duration::from_secs(10),
     }
 }
@@ -282,18 +631,61 @@ fn load_config() -> ApiConfig {
 fn rocket() -> _ {
     let config = load_config();
 
+    persist::init(config.cache_path.as_deref());
+
     let client = Client::builder()
         .user_agent("lx-tfl-api/0.1")
         .build()
         .expect("Failed to build HTTP client");
 
-    let state = AppState {
+    let (arrivals_tx, _) = broadcast::channel(16);
+
+    // Seed the cache from disk so a restart doesn't force a cold upstream
+    // call for stops whose persisted data is still within TTL.
+    let mut cache = HashMap::new();
+    for stop_id in &config.stop_ids {
+        if let Some((data, fetched_at)) = persist::load(stop_id) {
+            if Utc::now().signed_duration_since(fetched_at) < chrono::Duration::from_std(config.cache_ttl).unwrap_or_default() {
+                cache.insert(
+                    stop_id.clone(),
+                    Cache {
+                        last_fetch: Instant::now(),
+                        fetched_at,
+                        data,
+                    },
+                );
+            }
+        }
+    }
+
+    let state = Arc::new(AppState {
         client,
         config,
-        cache: Mutex::new(None),
-    };
+        cache: Mutex::new(cache),
+        arrivals_tx,
+        poller_status: Mutex::new(HashMap::new()),
+    });
 
     rocket::build()
         .manage(state)
-        .mount("/", routes![next_bus, next_bus_summary])
+        .mount(
+            "/",
+            routes![
+                next_bus,
+                next_bus_summary,
+                next_bus_stream,
+                stop_next_bus,
+                journey,
+                health
+            ],
+        )
+        .attach(AdHoc::on_liftoff("Arrivals Poller", |rocket| {
+            Box::pin(async move {
+                let state = rocket
+                    .state::<Arc<AppState>>()
+                    .expect("AppState is managed")
+                    .clone();
+                run_poller(state);
+            })
+        }))
 }
@@ -0,0 +1,62 @@
+use std::sync::OnceLock;
+
+use chrono::{DateTime, Utc};
+use rocket::serde::json::serde_json;
+use rocket::serde::{Deserialize, Serialize};
+
+use crate::tfl::Arrival;
+
+/// The on-disk cache, opened once at launch from `TFL_CACHE_PATH`. `None`
+/// when unset, in which case every function here is a no-op and the service
+/// behaves exactly as it did with memory-only caching.
+static STORE: OnceLock<Option<sled::Db>> = OnceLock::new();
+
+#[derive(Serialize, Deserialize)]
+struct PersistedEntry {
+    fetched_at: DateTime<Utc>,
+    data: Vec<Arrival>,
+}
+
+/// Opens the sled store at `path`, if any. Must be called exactly once,
+/// before [`load`]/[`store`] are used, which `rocket()` does at startup.
+pub fn init(path: Option<&str>) {
+    STORE.get_or_init(|| {
+        path.map(|p| sled::open(p).unwrap_or_else(|e| panic!("failed to open TFL_CACHE_PATH {}: {}", p, e)))
+    });
+}
+
+fn db() -> Option<&'static sled::Db> {
+    STORE.get().and_then(|store| store.as_ref())
+}
+
+/// Loads the persisted arrivals for `stop_id`, if the store is enabled and
+/// holds an entry for it.
+pub fn load(stop_id: &str) -> Option<(Vec<Arrival>, DateTime<Utc>)> {
+    let entry = db()?.get(stop_id).ok()??;
+    let entry: PersistedEntry = serde_json::from_slice(&entry).ok()?;
+    Some((entry.data, entry.fetched_at))
+}
+
+/// Persists `data` for `stop_id` under its fetch timestamp. A no-op if no
+/// `TFL_CACHE_PATH` was configured.
+pub fn store(stop_id: &str, data: &[Arrival], fetched_at: DateTime<Utc>) {
+    let Some(db) = db() else { return };
+
+    let entry = PersistedEntry {
+        fetched_at,
+        data: data.to_vec(),
+    };
+
+    let Ok(bytes) = serde_json::to_vec(&entry) else {
+        return;
+    };
+
+    if let Err(e) = db.insert(stop_id, bytes) {
+        rocket::error!("failed to persist arrivals cache for stop {}: {}", stop_id, e);
+        return;
+    }
+
+    if let Err(e) = db.flush() {
+        rocket::error!("failed to flush arrivals cache store: {}", e);
+    }
+}
@@ -0,0 +1,172 @@
+use std::time::Duration;
+
+use reqwest::Client;
+use rocket::serde::{Deserialize, Serialize};
+
+use crate::ApiConfig;
+use crate::tfl::TflError;
+
+pub fn build_stop_search_url(query: &str, app_id: Option<&str>, app_key: Option<&str>) -> String {
+    let mut base = format!(
+        "https://api.tfl.gov.uk/StopPoint/Search/{}",
+        urlencoding::encode(query)
+    );
+
+    let mut params = vec![];
+    if let Some(app_id) = app_id {
+        params.push(format!("app_id={}", urlencoding::encode(app_id)));
+    }
+    if let Some(app_key) = app_key {
+        params.push(format!("app_key={}", urlencoding::encode(app_key)));
+    }
+    if !params.is_empty() {
+        base.push('?');
+        base.push_str(&params.join("&"));
+    }
+
+    base
+}
+
+pub fn build_journey_url(
+    from_id: &str,
+    to_id: &str,
+    time: Option<&str>,
+    app_id: Option<&str>,
+    app_key: Option<&str>,
+) -> String {
+    let mut base = format!(
+        "https://api.tfl.gov.uk/Journey/JourneyResults/{}/to/{}",
+        urlencoding::encode(from_id),
+        urlencoding::encode(to_id),
+    );
+
+    let mut params = vec![];
+    if let Some(time) = time {
+        params.push(format!("time={}", urlencoding::encode(time)));
+    }
+    if let Some(app_id) = app_id {
+        params.push(format!("app_id={}", urlencoding::encode(app_id)));
+    }
+    if let Some(app_key) = app_key {
+        params.push(format!("app_key={}", urlencoding::encode(app_key)));
+    }
+    if !params.is_empty() {
+        base.push('?');
+        base.push_str(&params.join("&"));
+    }
+
+    base
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct JourneyLeg {
+    pub mode: JourneyMode,
+    #[serde(rename = "departureTime")]
+    pub departure_time: String,
+    #[serde(rename = "arrivalTime")]
+    pub arrival_time: String,
+    pub instruction: LegInstruction,
+    pub duration: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct JourneyMode {
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LegInstruction {
+    pub summary: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Journey {
+    pub duration: i64,
+    #[serde(rename = "startDateTime")]
+    pub start_date_time: String,
+    #[serde(rename = "arrivalDateTime")]
+    pub arrival_date_time: String,
+    pub legs: Vec<JourneyLeg>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JourneyResultsResponse {
+    journeys: Vec<Journey>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StopPointSearchResponse {
+    matches: Vec<StopPointMatch>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StopPointMatch {
+    id: String,
+    name: String,
+}
+
+/// Resolves a free-text place name to a StopPoint id, the way a human typing
+/// a station name into a journey planner expects: an exact case-insensitive
+/// name match wins, otherwise TfL's own relevance ranking (the first result)
+/// is trusted.
+async fn resolve_stop(config: &ApiConfig, client: &Client, name: &str) -> Result<String, TflError> {
+    let url = config.build_stop_search_url(name);
+
+    let resp = client
+        .get(&url)
+        .timeout(Duration::from_secs(5))
+        .send()
+        .await?;
+
+    if !resp.status().is_success() {
+        let code = resp.status();
+        let text = resp.text().await.unwrap_or_default();
+        return Err(TflError::UpstreamError(code, text));
+    }
+
+    let search: StopPointSearchResponse = resp.json().await?;
+
+    search
+        .matches
+        .iter()
+        .find(|m| m.name.eq_ignore_ascii_case(name))
+        .or_else(|| search.matches.first())
+        .map(|m| m.id.clone())
+        .ok_or(TflError::NoJourney)
+}
+
+/// Plans journeys between two free-text place names: resolves each to a
+/// StopPoint id via [`resolve_stop`], then asks the Journey Planner for
+/// itineraries between them, optionally at a given departure `time`.
+pub async fn plan_journey(
+    config: &ApiConfig,
+    client: &Client,
+    from: &str,
+    to: &str,
+    time: Option<&str>,
+) -> Result<Vec<Journey>, TflError> {
+    let from_id = resolve_stop(config, client, from).await?;
+    let to_id = resolve_stop(config, client, to).await?;
+
+    let url = config.build_journey_url(&from_id, &to_id, time);
+
+    let resp = client
+        .get(&url)
+        .timeout(Duration::from_secs(5))
+        .send()
+        .await?;
+
+    if !resp.status().is_success() {
+        let code = resp.status();
+        let text = resp.text().await.unwrap_or_default();
+        return Err(TflError::UpstreamError(code, text));
+    }
+
+    let results: JourneyResultsResponse = resp.json().await?;
+
+    if results.journeys.is_empty() {
+        return Err(TflError::NoJourney);
+    }
+
+    Ok(results.journeys)
+}
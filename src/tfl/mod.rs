@@ -6,6 +6,8 @@ use rocket::serde::{Deserialize, Serialize};
 
 use crate::ApiConfig;
 
+pub mod journey;
+
 pub fn build_tfl_url(stop_id: &str, app_id: Option<&str>, app_key: Option<&str>) -> String {
     let mut base = format!(
         "https://api.tfl.gov.uk/StopPoint/{}/Arrivals",
@@ -86,10 +88,16 @@ pub enum TflError {
     UpstreamError(StatusCode, String),
     #[error("Parse error")]
     ParseError(#[from] reqwest::Error),
+    #[error("No journey found")]
+    NoJourney,
 }
 
-pub async fn fetch_arrivals(config: &ApiConfig, client: &Client) -> Result<Vec<Arrival>, TflError> {
-    let url = config.build_tfl_url();
+pub async fn fetch_arrivals(
+    config: &ApiConfig,
+    client: &Client,
+    stop_id: &str,
+) -> Result<Vec<Arrival>, TflError> {
+    let url = config.build_tfl_url(stop_id);
 
     let resp = client
         .get(&url)